@@ -0,0 +1,279 @@
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{instruction::Instruction, msg};
+use std::convert::TryInto;
+use thiserror::Error;
+
+// On-chain layout of a safe's whitelist account:
+//
+//   [0..32)   safe             - the `Safe` this whitelist belongs to.
+//   [32..36)  len               - number of populated entries (u32 LE).
+//   [36..)    entries           - `len` program ids, 32 bytes each.
+const SAFE_LEN: usize = 32;
+const LEN_LEN: usize = 4;
+const ENTRY_LEN: usize = 32;
+const HEADER_LEN: usize = SAFE_LEN + LEN_LEN;
+
+// Seed for the whitelist account's PDA, derived per-safe so a caller can
+// never substitute an account they control for the safe's real whitelist.
+const WHITELIST_PDA_SEED: &[u8] = b"whitelist";
+
+/// A read-only view of the on-chain whitelist account associated with a
+/// `Safe`, i.e. the set of program ids the safe's locked funds may be
+/// signed into via CPI.
+pub struct Whitelist {
+    safe: Pubkey,
+    entries: Vec<u8>,
+}
+
+impl Whitelist {
+    pub fn new(acc_info: &AccountInfo, safe: &Pubkey) -> Result<Self, LockupError> {
+        if acc_info.owner != &serum_lockup::ID {
+            return Err(LockupErrorCode::WhitelistAccountInvalid)?;
+        }
+        let (expected_key, _bump) =
+            Pubkey::find_program_address(&[WHITELIST_PDA_SEED, safe.as_ref()], &serum_lockup::ID);
+        if acc_info.key != &expected_key {
+            return Err(LockupErrorCode::WhitelistAccountInvalid)?;
+        }
+
+        let data = acc_info
+            .try_borrow_data()
+            .map_err(|_| LockupErrorCode::WhitelistAccountInvalid)?;
+        if data.len() < HEADER_LEN {
+            return Err(LockupErrorCode::WhitelistAccountInvalid)?;
+        }
+        let stored_safe = Pubkey::new(&data[..SAFE_LEN]);
+        if &stored_safe != safe {
+            return Err(LockupErrorCode::WhitelistAccountInvalid)?;
+        }
+        let len = u32::from_le_bytes(
+            data[SAFE_LEN..HEADER_LEN]
+                .try_into()
+                .map_err(|_| LockupErrorCode::WhitelistAccountInvalid)?,
+        ) as usize;
+        let entries_end = HEADER_LEN + len * ENTRY_LEN;
+        if data.len() < entries_end {
+            return Err(LockupErrorCode::WhitelistAccountInvalid)?;
+        }
+        Ok(Self {
+            safe: stored_safe,
+            entries: data[HEADER_LEN..entries_end].to_vec(),
+        })
+    }
+
+    /// The PDA a safe's whitelist account must live at.
+    pub fn derive_address(safe: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[WHITELIST_PDA_SEED, safe.as_ref()], &serum_lockup::ID)
+    }
+
+    pub fn safe(&self) -> &Pubkey {
+        &self.safe
+    }
+
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.entries
+            .chunks_exact(ENTRY_LEN)
+            .any(|entry| entry == program_id.as_ref())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LockupErrorCode {
+    #[error("the whitelist account does not belong to the given safe or is malformed")]
+    WhitelistAccountInvalid,
+    #[error("the CPI target program is not on the safe's whitelist")]
+    WhitelistCpiNotWhitelisted,
+    #[error("the vault authority may not be passed as a writable account to a whitelisted CPI")]
+    WhitelistCpiUnauthorizedVaultAccount,
+}
+
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct LockupError(#[from] LockupErrorCode);
+
+impl From<LockupError> for ProgramError {
+    fn from(e: LockupError) -> ProgramError {
+        ProgramError::Custom(match e.0 {
+            LockupErrorCode::WhitelistAccountInvalid => 150,
+            LockupErrorCode::WhitelistCpiNotWhitelisted => 151,
+            LockupErrorCode::WhitelistCpiUnauthorizedVaultAccount => 152,
+        })
+    }
+}
+
+// Asserts that `instruction.program_id` is a member of the safe's on-chain
+// whitelist, and that the vault authority PDA is never smuggled into the
+// instruction as a writable account. The whitelist only records which
+// programs are approved CPI targets; it has no notion of an arbitrary
+// whitelisted program's account layout, so that's the only vault-authority
+// invariant we can enforce generically here — a writable vault authority
+// has no legitimate purpose, since it's a PDA authority, never a token
+// account itself.
+pub fn whitelist_cpi(
+    whitelist_acc_info: &AccountInfo,
+    safe: &Pubkey,
+    instruction: &Instruction,
+    vault_authority: &Pubkey,
+) -> Result<(), LockupError> {
+    let whitelist = Whitelist::new(whitelist_acc_info, safe)?;
+    if !whitelist.contains(&instruction.program_id) {
+        msg!("whitelist-cpi: program not whitelisted");
+        return Err(LockupErrorCode::WhitelistCpiNotWhitelisted)?;
+    }
+    let smuggled = instruction
+        .accounts
+        .iter()
+        .any(|meta| &meta.pubkey == vault_authority && meta.is_writable);
+    if smuggled {
+        msg!("whitelist-cpi: vault authority passed as a writable account");
+        return Err(LockupErrorCode::WhitelistCpiUnauthorizedVaultAccount)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::system_program;
+
+    fn whitelist_account_data(safe: &Pubkey, programs: &[Pubkey]) -> Vec<u8> {
+        let mut data = safe.as_ref().to_vec();
+        data.extend_from_slice(&(programs.len() as u32).to_le_bytes());
+        for p in programs {
+            data.extend_from_slice(p.as_ref());
+        }
+        data
+    }
+
+    fn whitelist_account_info<'a>(
+        key: &'a Pubkey,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, false, false, lamports, data, owner, false, 0)
+    }
+
+    #[test]
+    fn rejects_non_whitelisted_program() {
+        let safe = Pubkey::new_unique();
+        let whitelisted_program = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        let mut data = whitelist_account_data(&safe, &[whitelisted_program]);
+        let (key, _bump) = Whitelist::derive_address(&safe);
+        let owner = serum_lockup::ID;
+        let mut lamports = 0;
+        let whitelist_acc_info = whitelist_account_info(&key, &owner, &mut lamports, &mut data);
+
+        let vault_authority = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: other_program,
+            accounts: vec![],
+            data: vec![],
+        };
+
+        let err = whitelist_cpi(&whitelist_acc_info, &safe, &instruction, &vault_authority)
+            .unwrap_err();
+        assert!(matches!(err.0, LockupErrorCode::WhitelistCpiNotWhitelisted));
+    }
+
+    #[test]
+    fn rejects_whitelist_account_not_owned_by_lockup_program() {
+        let safe = Pubkey::new_unique();
+        let whitelisted_program = Pubkey::new_unique();
+        let mut data = whitelist_account_data(&safe, &[whitelisted_program]);
+        let (key, _bump) = Whitelist::derive_address(&safe);
+        // An attacker-controlled account at the right address but owned by
+        // some other (e.g. attacker-deployed) program.
+        let owner = system_program::ID;
+        let mut lamports = 0;
+        let whitelist_acc_info = whitelist_account_info(&key, &owner, &mut lamports, &mut data);
+
+        let vault_authority = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: whitelisted_program,
+            accounts: vec![],
+            data: vec![],
+        };
+
+        let err = whitelist_cpi(&whitelist_acc_info, &safe, &instruction, &vault_authority)
+            .unwrap_err();
+        assert!(matches!(err.0, LockupErrorCode::WhitelistAccountInvalid));
+    }
+
+    #[test]
+    fn rejects_whitelist_account_at_the_wrong_address() {
+        let safe = Pubkey::new_unique();
+        let whitelisted_program = Pubkey::new_unique();
+        let mut data = whitelist_account_data(&safe, &[whitelisted_program]);
+        // Correctly owned, but not the safe's canonical whitelist PDA -- an
+        // attacker could otherwise stand up their own lockup-program-owned
+        // account (e.g. a second safe's whitelist) with forged data.
+        let key = Pubkey::new_unique();
+        let owner = serum_lockup::ID;
+        let mut lamports = 0;
+        let whitelist_acc_info = whitelist_account_info(&key, &owner, &mut lamports, &mut data);
+
+        let vault_authority = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: whitelisted_program,
+            accounts: vec![],
+            data: vec![],
+        };
+
+        let err = whitelist_cpi(&whitelist_acc_info, &safe, &instruction, &vault_authority)
+            .unwrap_err();
+        assert!(matches!(err.0, LockupErrorCode::WhitelistAccountInvalid));
+    }
+
+    #[test]
+    fn rejects_smuggled_writable_vault_authority() {
+        use solana_sdk::instruction::AccountMeta;
+
+        let safe = Pubkey::new_unique();
+        let whitelisted_program = Pubkey::new_unique();
+        let mut data = whitelist_account_data(&safe, &[whitelisted_program]);
+        let (key, _bump) = Whitelist::derive_address(&safe);
+        let owner = serum_lockup::ID;
+        let mut lamports = 0;
+        let whitelist_acc_info = whitelist_account_info(&key, &owner, &mut lamports, &mut data);
+
+        let vault_authority = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: whitelisted_program,
+            accounts: vec![AccountMeta::new(vault_authority, false)],
+            data: vec![],
+        };
+
+        let err = whitelist_cpi(&whitelist_acc_info, &safe, &instruction, &vault_authority)
+            .unwrap_err();
+        assert!(matches!(
+            err.0,
+            LockupErrorCode::WhitelistCpiUnauthorizedVaultAccount
+        ));
+    }
+
+    #[test]
+    fn allows_whitelisted_program_with_readonly_vault_authority() {
+        use solana_sdk::instruction::AccountMeta;
+
+        let safe = Pubkey::new_unique();
+        let whitelisted_program = Pubkey::new_unique();
+        let mut data = whitelist_account_data(&safe, &[whitelisted_program]);
+        let (key, _bump) = Whitelist::derive_address(&safe);
+        let owner = serum_lockup::ID;
+        let mut lamports = 0;
+        let whitelist_acc_info = whitelist_account_info(&key, &owner, &mut lamports, &mut data);
+
+        let vault_authority = Pubkey::new_unique();
+        let instruction = Instruction {
+            program_id: whitelisted_program,
+            accounts: vec![AccountMeta::new_readonly(vault_authority, true)],
+            data: vec![],
+        };
+
+        assert!(whitelist_cpi(&whitelist_acc_info, &safe, &instruction, &vault_authority).is_ok());
+    }
+}
@@ -12,8 +12,12 @@ pub fn whitelist_cpi(
     safe: &Pubkey,
     beneficiary_acc_info: &AccountInfo,
     vesting: &Vesting,
+    whitelist: &AccountInfo,
     accounts: &[AccountInfo],
 ) -> ProgramResult {
     let signer_seeds = vault::signer_seeds(safe, beneficiary_acc_info.key, &vesting.nonce);
+    let vault_authority =
+        Pubkey::create_program_address(&signer_seeds, &serum_lockup::ID).unwrap();
+    access_control::whitelist_cpi(whitelist, safe, &instruction, &vault_authority)?;
     solana_sdk::program::invoke_signed(&instruction, accounts, &[&signer_seeds])
 }
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A Solana cluster the rewards `Client` can target, mirroring the
+/// `mainnet-beta` / `devnet` / `testnet` / `localnet` monikers used
+/// throughout the Solana and Anchor tooling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+    Custom(String),
+}
+
+impl Cluster {
+    /// The cluster's JSON-RPC HTTP endpoint.
+    pub fn url(&self) -> &str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Custom(url) => url,
+        }
+    }
+
+    /// The cluster's pubsub (logs/account subscription) endpoint.
+    ///
+    /// `solana-test-validator` serves its websocket pubsub on port 8900,
+    /// one above its JSON-RPC port, so `Localnet` (and any `Custom` url
+    /// pointing at the standard RPC port 8899) is special-cased rather
+    /// than just swapping the `http` scheme for `ws`.
+    pub fn ws_url(&self) -> String {
+        match self {
+            Cluster::Localnet => "ws://127.0.0.1:8900".to_string(),
+            _ => ws_url_from_http(self.url()),
+        }
+    }
+}
+
+pub(crate) fn ws_url_from_http(url: &str) -> String {
+    let ws = url.replacen("https://", "wss://", 1).replacen("http://", "ws://", 1);
+    ws.replacen(":8899", ":8900", 1)
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Cluster::Mainnet => "mainnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+            Cluster::Custom(url) => url,
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = ClusterParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" | "mainnet-beta" => Ok(Cluster::Mainnet),
+            "devnet" => Ok(Cluster::Devnet),
+            "testnet" => Ok(Cluster::Testnet),
+            "localnet" | "localhost" => Ok(Cluster::Localnet),
+            _ if s.starts_with("http://") || s.starts_with("https://") => {
+                Ok(Cluster::Custom(s.to_string()))
+            }
+            _ => Err(ClusterParseError(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("unrecognized cluster moniker or URL: {0}")]
+pub struct ClusterParseError(String);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localnet_ws_url_uses_the_validator_pubsub_port() {
+        assert_eq!(Cluster::Localnet.ws_url(), "ws://127.0.0.1:8900");
+    }
+
+    #[test]
+    fn custom_localhost_url_also_gets_the_pubsub_port_bump() {
+        let cluster = Cluster::Custom("http://127.0.0.1:8899".to_string());
+        assert_eq!(cluster.ws_url(), "ws://127.0.0.1:8900");
+    }
+
+    #[test]
+    fn mainnet_ws_url_just_swaps_the_scheme() {
+        assert_eq!(
+            Cluster::Mainnet.ws_url(),
+            "wss://api.mainnet-beta.solana.com"
+        );
+    }
+}
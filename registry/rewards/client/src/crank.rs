@@ -0,0 +1,216 @@
+use crate::{Client, ClientError, ClientGen, CrankRelayRequest};
+use serum_dex::instruction::consume_events;
+use serum_dex::state::{EventQueueHeader, EVENT_QUEUE_HEADER_LEN};
+use solana_client_gen::prelude::Signer;
+use solana_client_gen::solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// Configuration for a long-lived `crank_relay` daemon, relaying
+/// `consume_events` on a single dex market until its event queue drains.
+#[derive(Debug, Clone)]
+pub struct CrankConfig {
+    pub instance: Pubkey,
+    pub entity: Pubkey,
+    pub token_account: Pubkey,
+    pub dex_program_id: Pubkey,
+    pub market: Pubkey,
+    pub event_q: Pubkey,
+    pub open_orders_accounts: Vec<Pubkey>,
+    /// Where the dex sweeps accumulated coin-side fees on each consumed fill.
+    pub coin_fee_receivable_account: Pubkey,
+    /// Where the dex sweeps accumulated pc-side fees on each consumed fill.
+    pub pc_fee_receivable_account: Pubkey,
+    /// Max number of events consumed per `consume_events` call.
+    pub batch_size: u16,
+    /// Time to sleep between polls of the event queue.
+    pub poll_interval: Duration,
+    /// Ceiling on the exponential backoff applied after an RPC error.
+    pub max_backoff: Duration,
+}
+
+impl Default for CrankConfig {
+    fn default() -> Self {
+        Self {
+            instance: Pubkey::default(),
+            entity: Pubkey::default(),
+            token_account: Pubkey::default(),
+            dex_program_id: Pubkey::default(),
+            market: Pubkey::default(),
+            event_q: Pubkey::default(),
+            open_orders_accounts: Vec::new(),
+            coin_fee_receivable_account: Pubkey::default(),
+            pc_fee_receivable_account: Pubkey::default(),
+            batch_size: 16,
+            poll_interval: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// The outcome of a single poll-crank-or-skip iteration of the daemon loop.
+#[derive(Debug, Clone)]
+pub enum CrankIterationOutcome {
+    /// The event queue was empty; nothing to do.
+    QueueEmpty,
+    /// A `consume_events` simulation failed, so nothing was sent.
+    SimulationFailed,
+    /// The crank landed.
+    Cranked(Signature),
+    /// The iteration could not complete because an RPC call failed. This is
+    /// distinct from `QueueEmpty` so operators can alert on sustained RPC
+    /// failure rather than mistake it for a quiet market.
+    Error(String),
+}
+
+/// A report of a single iteration of the daemon loop, handed to the
+/// `on_iteration` callback in `run`.
+#[derive(Debug, Clone)]
+pub struct CrankIterationReport {
+    /// `None` when the queue length couldn't be read (see `outcome`).
+    pub queue_len: Option<usize>,
+    pub outcome: CrankIterationOutcome,
+}
+
+/// Runs the crank-relay daemon loop forever, relaying `consume_events`
+/// transactions via `Client::crank_relay` until interrupted.
+///
+/// On each iteration, the dex event queue length is read; if it's empty
+/// the iteration is skipped. Otherwise a `consume_events` instruction
+/// covering at most `config.batch_size` events is built, simulated, and,
+/// if simulation succeeds, relayed through `crank_relay`. `on_iteration`
+/// is called with a `CrankIterationReport` after every iteration,
+/// including failed ones, so operators can distinguish "nothing to
+/// crank" from "RPC is failing". RPC errors back off exponentially, up
+/// to `config.max_backoff`, and do not terminate the loop.
+pub fn run<F>(client: &Client, config: CrankConfig, mut on_iteration: F) -> !
+where
+    F: FnMut(&CrankIterationReport),
+{
+    let mut backoff = config.poll_interval;
+    loop {
+        let report = run_iteration(client, &config);
+        let errored = matches!(report.outcome, CrankIterationOutcome::Error(_));
+        on_iteration(&report);
+        if errored {
+            sleep(backoff);
+            backoff = std::cmp::min(backoff * 2, config.max_backoff);
+        } else {
+            backoff = config.poll_interval;
+            sleep(config.poll_interval);
+        }
+    }
+}
+
+fn run_iteration(client: &Client, config: &CrankConfig) -> CrankIterationReport {
+    let queue_len = match event_queue_len(client, &config.event_q) {
+        Ok(len) => len,
+        Err(e) => {
+            return CrankIterationReport {
+                queue_len: None,
+                outcome: CrankIterationOutcome::Error(e.to_string()),
+            }
+        }
+    };
+    if queue_len == 0 {
+        return CrankIterationReport {
+            queue_len: Some(queue_len),
+            outcome: CrankIterationOutcome::QueueEmpty,
+        };
+    }
+
+    match run_crank(client, config, queue_len) {
+        Ok(outcome) => CrankIterationReport {
+            queue_len: Some(queue_len),
+            outcome,
+        },
+        Err(e) => CrankIterationReport {
+            queue_len: Some(queue_len),
+            outcome: CrankIterationOutcome::Error(e.to_string()),
+        },
+    }
+}
+
+fn run_crank(
+    client: &Client,
+    config: &CrankConfig,
+    queue_len: usize,
+) -> Result<CrankIterationOutcome, ClientError> {
+    let consume_events_instr = consume_events_ix(config, queue_len)?;
+    let req = CrankRelayRequest {
+        instance: config.instance,
+        token_account: config.token_account,
+        entity: config.entity,
+        dex_event_q: config.event_q,
+        consume_events_instr,
+    };
+    let ix = client.crank_relay_ix(req.clone())?;
+    if simulate(client, &ix)?.err.is_some() {
+        return Ok(CrankIterationOutcome::SimulationFailed);
+    }
+
+    let resp = client.crank_relay(req)?;
+    Ok(CrankIterationOutcome::Cranked(resp.tx))
+}
+
+fn event_queue_len(client: &Client, event_q: &Pubkey) -> Result<usize, ClientError> {
+    let account = client
+        .rpc()
+        .get_account(event_q)
+        .map_err(ClientError::RpcError)?;
+    let header_bytes = account.data.get(..EVENT_QUEUE_HEADER_LEN).ok_or_else(|| {
+        ClientError::Any(anyhow::anyhow!(
+            "event queue {} has fewer than {} bytes of data",
+            event_q,
+            EVENT_QUEUE_HEADER_LEN
+        ))
+    })?;
+    let header = EventQueueHeader::deserialize(header_bytes)
+        .map_err(|e| ClientError::Any(anyhow::anyhow!(e.to_string())))?;
+    Ok(header.count())
+}
+
+fn consume_events_ix(config: &CrankConfig, queue_len: usize) -> Result<Instruction, ClientError> {
+    let limit = std::cmp::min(queue_len, config.batch_size as usize) as u16;
+    consume_events(
+        config.dex_program_id,
+        config.open_orders_accounts.iter().collect(),
+        config.market,
+        config.event_q,
+        config.coin_fee_receivable_account,
+        config.pc_fee_receivable_account,
+        limit,
+    )
+    .map_err(|e| ClientError::Any(anyhow::anyhow!(e.to_string())))
+}
+
+fn simulate(
+    client: &Client,
+    ix: &Instruction,
+) -> Result<solana_client_gen::solana_client::rpc_response::RpcSimulateTransactionResult, ClientError> {
+    let (recent_hash, _) = client
+        .rpc()
+        .get_recent_blockhash()
+        .map_err(ClientError::RpcError)?;
+    let tx = Transaction::new_signed_with_payer(
+        &[ix.clone()],
+        Some(&client.payer().pubkey()),
+        &[client.payer()],
+        recent_hash,
+    );
+    client
+        .rpc()
+        .simulate_transaction_with_config(
+            &tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .map(|r| r.value)
+        .map_err(ClientError::RpcError)
+}
@@ -0,0 +1,139 @@
+use solana_client_gen::solana_client::pubsub_client::{PubsubClient, PubsubClientError};
+use solana_client_gen::solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_client_gen::solana_client::rpc_response::RpcLogsResponse;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+
+/// A decoded `crank_relay` program log, emitted once per successful crank.
+///
+/// The parser in [`CrankRelayEvent::parse`] assumes the on-chain
+/// `crank_relay` handler emits a `msg!()` line matching `LOG_PREFIX`
+/// exactly. That handler is not part of this crate, so this contract
+/// cannot be checked at compile time: **verify the deployed program's log
+/// text matches before relying on `Client::subscribe` in production.** If
+/// it doesn't match, `subscribe()` won't error — it will simply never
+/// yield an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrankRelayEvent {
+    pub instance: Pubkey,
+    pub entity: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+}
+
+const LOG_PREFIX: &str = "serum-registry-rewards: crank-relay:";
+
+impl CrankRelayEvent {
+    // Parses a single program log line of the form
+    //
+    //   "Program log: serum-registry-rewards: crank-relay: <instance> <entity> <token_account> <amount>"
+    //
+    // This is a contract with the on-chain `crank_relay` instruction
+    // handler's `msg!()` call: if that format ever changes, this parser
+    // and its test fixtures must change with it. See the unverified-format
+    // warning on `CrankRelayEvent` above.
+    fn parse(log: &str) -> Option<Self> {
+        let rest = log.split(LOG_PREFIX).nth(1)?.trim();
+        let mut fields = rest.split_whitespace();
+        let instance = Pubkey::from_str(fields.next()?).ok()?;
+        let entity = Pubkey::from_str(fields.next()?).ok()?;
+        let token_account = Pubkey::from_str(fields.next()?).ok()?;
+        let amount = fields.next()?.parse::<u64>().ok()?;
+        Some(CrankRelayEvent {
+            instance,
+            entity,
+            token_account,
+            amount,
+        })
+    }
+}
+
+/// A live subscription to `crank_relay` events for a single rewards program,
+/// backed by a websocket logs subscription. Dropping this drops the
+/// subscription.
+pub struct CrankRelaySubscription {
+    client: PubsubClient,
+    receiver: Receiver<RpcLogsResponse>,
+}
+
+impl CrankRelaySubscription {
+    pub(crate) fn new(
+        ws_url: &str,
+        program_id: Pubkey,
+    ) -> Result<Self, PubsubClientError> {
+        let (client, receiver) = PubsubClient::logs_subscribe(
+            ws_url,
+            RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+            RpcTransactionLogsConfig { commitment: None },
+        )?;
+        Ok(Self { client, receiver })
+    }
+
+    pub fn shutdown(self) -> Result<(), Box<dyn std::error::Error>> {
+        self.client.shutdown()
+    }
+}
+
+impl Iterator for CrankRelaySubscription {
+    type Item = CrankRelayEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let response = self.receiver.recv().ok()?;
+            if let Some(event) = response.logs.iter().find_map(|l| CrankRelayEvent::parse(l)) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_log(instance: &Pubkey, entity: &Pubkey, token_account: &Pubkey, amount: u64) -> String {
+        format!(
+            "Program log: serum-registry-rewards: crank-relay: {} {} {} {}",
+            instance, entity, token_account, amount
+        )
+    }
+
+    #[test]
+    fn parses_a_well_formed_crank_relay_log() {
+        let instance = Pubkey::new_unique();
+        let entity = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let log = sample_log(&instance, &entity, &token_account, 42);
+
+        let event = CrankRelayEvent::parse(&log).expect("log should parse");
+        assert_eq!(
+            event,
+            CrankRelayEvent {
+                instance,
+                entity,
+                token_account,
+                amount: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_unrelated_program_logs() {
+        assert_eq!(
+            CrankRelayEvent::parse("Program log: some other program's log line"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_a_truncated_crank_relay_log() {
+        let instance = Pubkey::new_unique();
+        let entity = Pubkey::new_unique();
+        let log = format!(
+            "Program log: serum-registry-rewards: crank-relay: {} {}",
+            instance, entity
+        );
+        assert_eq!(CrankRelayEvent::parse(&log), None);
+    }
+}
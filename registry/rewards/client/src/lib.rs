@@ -6,26 +6,42 @@ use serum_registry_rewards::error::RewardsError;
 use serum_registry_rewards::instruction;
 use solana_client_gen::prelude::Signer;
 use solana_client_gen::prelude::*;
+use solana_client_gen::solana_client::rpc_config::RpcSendTransactionConfig;
 use solana_client_gen::solana_sdk;
+use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::program_pack::Pack;
 use solana_sdk::signature::Signature;
 use spl_token::state::Account as TokenAccount;
 use std::convert::Into;
 use thiserror::Error;
 
+mod cluster;
+pub mod crank;
+mod event;
 mod inner;
 
+pub use cluster::{Cluster, ClusterParseError};
+pub use event::{CrankRelayEvent, CrankRelaySubscription};
 pub use serum_registry_rewards::*;
 pub use solana_client_gen::{ClientGen, RequestOptions};
 
 pub struct Client {
     inner: InnerClient,
+    token_program: Pubkey,
 }
 
 impl Client {
     pub fn new(inner: InnerClient) -> Self {
-        Self { inner }
+        Self::new_with_token_program(inner, spl_token::ID)
+    }
+
+    pub fn new_with_token_program(inner: InnerClient, token_program: Pubkey) -> Self {
+        Self {
+            inner,
+            token_program,
+        }
     }
 
     pub fn from(program_id: Pubkey, payer: &Keypair, url: &str) -> Self {
@@ -37,6 +53,59 @@ impl Client {
         ))
     }
 
+    /// Like `from`, but resolves the RPC endpoint from a named `Cluster`
+    /// instead of a raw URL.
+    pub fn from_cluster(program_id: Pubkey, payer: &Keypair, cluster: Cluster) -> Self {
+        Self::from(program_id, payer, cluster.url())
+    }
+
+    /// Like `from`, but detects the token program that owns `reward_mint`
+    /// (`spl_token` or `spl_token_2022`) and remembers it, so every
+    /// subsequent instruction is built against the correct program.
+    pub fn from_reward_mint(
+        program_id: Pubkey,
+        payer: &Keypair,
+        url: &str,
+        reward_mint: Pubkey,
+    ) -> Result<Self, ClientError> {
+        let inner = InnerClient::new(
+            program_id,
+            Keypair::from_bytes(&payer.to_bytes()).unwrap(),
+            url,
+            None,
+        );
+        let mint_account = inner
+            .rpc()
+            .get_account(&reward_mint)
+            .map_err(InnerClientError::RpcError)?;
+        Ok(Self::new_with_token_program(inner, mint_account.owner))
+    }
+
+    pub fn token_program(&self) -> Pubkey {
+        self.token_program
+    }
+
+    /// Sets the preflight behavior used by `crank_relay`'s send, so cranking
+    /// can be made robust under cluster congestion (e.g. skipping
+    /// preflight simulation, relaxing the preflight commitment level, or
+    /// raising the leader-forwarding retry count).
+    pub fn with_send_config(
+        self,
+        skip_preflight: bool,
+        preflight_commitment: Option<CommitmentLevel>,
+        max_retries: Option<usize>,
+    ) -> Self {
+        let mut opts = self.inner.options().clone();
+        opts.tx = RpcSendTransactionConfig {
+            skip_preflight,
+            preflight_commitment,
+            max_retries,
+            ..RpcSendTransactionConfig::default()
+        };
+        let token_program = self.token_program;
+        Self::new_with_token_program(self.inner.with_options(opts), token_program)
+    }
+
     pub fn initialize(&self, req: InitializeRequest) -> Result<InitializeResponse, ClientError> {
         let (tx, instance, nonce) = inner::initialize(
             &self.inner,
@@ -79,6 +148,15 @@ impl Client {
         Ok(CrankRelayResponse { tx: sig })
     }
 
+    /// Subscribes to `crank_relay` events for this program over the
+    /// cluster's websocket pubsub endpoint, returning an iterator that
+    /// yields one decoded `CrankRelayEvent` per observed crank.
+    pub fn subscribe(&self) -> Result<CrankRelaySubscription, ClientError> {
+        let ws_url = cluster::ws_url_from_http(&self.inner.rpc().url());
+        CrankRelaySubscription::new(&ws_url, *self.program())
+            .map_err(|e| ClientError::Any(anyhow!(e.to_string())))
+    }
+
     pub fn crank_relay_ix(&self, req: CrankRelayIxRequest) -> Result<Instruction, ClientError> {
         let CrankRelayIxRequest {
             instance,
@@ -102,7 +180,7 @@ impl Client {
             AccountMeta::new(token_account, false),
             AccountMeta::new_readonly(entity, false),
             AccountMeta::new_readonly(entity_leader.pubkey(), true),
-            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(self.token_program, false),
             AccountMeta::new_readonly(consume_events_instr.program_id, false),
             AccountMeta::new(dex_event_q, false),
         ];
@@ -153,7 +231,7 @@ impl Client {
             AccountMeta::new(i.vault, false),
             AccountMeta::new_readonly(vault_authority, false),
             AccountMeta::new(receiver, false),
-            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(self.token_program, false),
         ];
         let signers = [authority, self.payer()];
         let tx = self.inner.migrate_with_signers(&signers, &accounts)?;
@@ -169,8 +247,21 @@ impl Client {
 
     pub fn vault(&self, instance: Pubkey) -> Result<TokenAccount, ClientError> {
         let instance = self.instance(instance)?;
-        rpc::get_token_account::<TokenAccount>(self.inner.rpc(), &instance.vault)
-            .map_err(Into::into)
+        if self.token_program == spl_token::ID {
+            return rpc::get_token_account::<TokenAccount>(self.inner.rpc(), &instance.vault)
+                .map_err(Into::into);
+        }
+        // `rpc::get_token_account` only knows the legacy `spl_token` account
+        // layout. Token-2022 lays out the same base fields before its TLV
+        // extension data, so read the account ourselves and unpack just
+        // that prefix; extension data (if any) isn't surfaced here.
+        let account = self
+            .inner
+            .rpc()
+            .get_account(&instance.vault)
+            .map_err(InnerClientError::RpcError)?;
+        TokenAccount::unpack(&account.data[..TokenAccount::LEN])
+            .map_err(|e| ClientError::Any(anyhow!(e.to_string())))
     }
 }
 
@@ -182,7 +273,8 @@ impl solana_client_gen::prelude::ClientGen for Client {
         ))
     }
     fn with_options(self, opts: RequestOptions) -> Client {
-        Self::new(self.inner.with_options(opts))
+        let token_program = self.token_program;
+        Self::new_with_token_program(self.inner.with_options(opts), token_program)
     }
     fn rpc(&self) -> &RpcClient {
         self.inner.rpc()
@@ -223,6 +315,7 @@ pub struct InitializeResponse {
     pub nonce: u8,
 }
 
+#[derive(Clone)]
 pub struct CrankRelayRequest {
     pub instance: Pubkey,
     pub token_account: Pubkey,